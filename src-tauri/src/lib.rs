@@ -1,17 +1,534 @@
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{Emitter, Manager};
+use tauri_plugin_cli::CliExt;
+use tauri_plugin_fs::FsExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/// Default accelerator used to summon or hide the window, mirroring the
+/// quick-note launchers users expect to be bound to a chord like this.
+const DEFAULT_SHORTCUT: &str = "CmdOrCtrl+Shift+Space";
+
+/// Tracks the in-flight generation for each conversation so the UI can cancel
+/// one that is still streaming. A conversation maps to the flag the worker
+/// polls between tokens; flipping it to `true` asks the worker to stop.
+#[derive(Default)]
+struct Generations {
+    cancels: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// A single conversation tracked by the app, kept lightweight enough to drive
+/// the tray's "recent" shortcuts.
+#[derive(Clone)]
+struct Conversation {
+    id: String,
+    title: String,
+}
+
+/// In-memory store of conversations, most-recent last.
+#[derive(Default)]
+struct ConversationStore {
+    conversations: Vec<Conversation>,
+}
+
+/// Handle to the local model runtime. A placeholder until inference is wired
+/// up — it exists so the runtime can be loaded once and kept resident across
+/// sessions rather than reloaded on every launch.
+#[derive(Default)]
+struct ModelRuntime;
+
+/// Shared application state, injected into commands via [`tauri::State`]. Gives
+/// commands a single, safe handle to the model runtime, the conversation store,
+/// in-flight generations and the persisted config.
+#[derive(Default)]
+struct AppState {
+    model: Arc<ModelRuntime>,
+    conversations: Mutex<ConversationStore>,
+    config: Mutex<Settings>,
+    generations: Generations,
+}
+
+/// Payload for the incremental `llm-token` events emitted while a response is
+/// being produced.
+#[derive(Clone, Serialize)]
+struct TokenEvent {
+    conversation_id: String,
+    delta: String,
+}
+
+/// Payload shared by the terminal `llm-done` / `llm-error` events.
+#[derive(Clone, Serialize)]
+struct DoneEvent {
+    conversation_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Payload for the `init-progress` events emitted while the model is loading so
+/// the splashscreen can render a real progress bar.
+#[derive(Clone, Serialize)]
+struct InitProgress {
+    value: u8,
+    status: String,
+}
+
+/// Persisted user settings, stored as `settings.json` in the app config
+/// directory. Grown field by field as the app gains configurable surfaces.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shortcut: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    theme: Option<String>,
+    /// When set (the default), closing the window hides it to the tray and
+    /// keeps the model resident instead of exiting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimize_to_tray: Option<bool>,
+}
+
+/// Resolve the path to the on-disk settings file, creating the config
+/// directory if it does not exist yet.
+fn settings_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("settings.json"))
+}
+
+/// Read the persisted settings, falling back to defaults when the file is
+/// absent or unreadable. Goes through `tauri_plugin_fs` so all disk access
+/// shares the plugin's scope and configuration.
+fn load_settings(app: &tauri::AppHandle) -> Settings {
+    settings_path(app)
+        .ok()
+        .and_then(|path| app.fs().read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Write the settings back to disk via `tauri_plugin_fs`.
+fn save_settings(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    let raw = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    app.fs()
+        .write(path, raw.into_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// Snapshot of the managed [`Settings`] — the in-memory source of truth seeded
+/// from disk at startup. Command handlers read this rather than hitting disk.
+fn current_settings(app: &tauri::AppHandle) -> Settings {
+    app.state::<AppState>().config.lock().unwrap().clone()
+}
+
+/// Mutate the managed settings and persist the result. Keeps the in-memory
+/// [`AppState::config`] and the on-disk `settings.json` in lockstep so neither
+/// goes stale.
+fn update_settings<F>(app: &tauri::AppHandle, mutate: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Settings),
+{
+    let state = app.state::<AppState>();
+    let mut config = state.config.lock().unwrap();
+    mutate(&mut config);
+    save_settings(app, &config)
+}
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-fn set_app_theme(window: tauri::Window, theme: String) {
-    let theme_enum = match theme.as_str() {
+/// Map a preference string to an explicit [`tauri::Theme`]. `"system"` (and any
+/// unknown value) resolves to `None`, which hands theming back to the OS.
+fn theme_from_pref(pref: &str) -> Option<tauri::Theme> {
+    match pref {
         "dark" => Some(tauri::Theme::Dark),
         "light" => Some(tauri::Theme::Light),
         _ => None,
+    }
+}
+
+/// Apply a theme preference to the `main` window. Nothing is returned or
+/// emitted; a missing `main` window or a failed `set_theme` is ignored.
+fn apply_theme(app: &tauri::AppHandle, pref: &str) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_theme(theme_from_pref(pref));
+    }
+}
+
+/// Set and persist the theme preference. Accepts `"dark"`, `"light"` or
+/// `"system"`; the latter lets the OS drive the theme and live-follows its
+/// changes (see the window event wiring in `setup`).
+#[tauri::command]
+fn set_app_theme(app: tauri::AppHandle, theme: String) -> Result<(), String> {
+    apply_theme(&app, &theme);
+    update_settings(&app, |settings| {
+        settings.theme = Some(theme);
+    })
+}
+
+/// Start streaming a model response for `conversation_id`. The heavy work runs
+/// on the async runtime so IPC is never blocked; each produced token is pushed
+/// to the webview as an `llm-token` event and the stream is closed with either
+/// `llm-done` or `llm-error`.
+#[tauri::command]
+fn generate(
+    window: tauri::Window,
+    state: tauri::State<'_, AppState>,
+    prompt: String,
+    conversation_id: String,
+) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state
+        .generations
+        .cancels
+        .lock()
+        .unwrap()
+        .insert(conversation_id.clone(), cancel.clone())
+    {
+        // A generation for this conversation is already running; ask it to stop
+        // so it doesn't race this one. Its completion leaves our freshly-inserted
+        // flag alone thanks to the `Arc::ptr_eq` check below.
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    // Hand the worker the resident model runtime so it decodes against the
+    // already-loaded weights instead of reloading them.
+    let model = state.model.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let result = stream_tokens(&model, &window, &conversation_id, &prompt, &cancel).await;
+
+        // The generation is finished; drop its cancellation flag so the map
+        // doesn't grow for the life of the session. Only remove our own flag —
+        // a newer generation for the same conversation may have replaced it.
+        if let std::collections::hash_map::Entry::Occupied(entry) = window
+            .app_handle()
+            .state::<AppState>()
+            .generations
+            .cancels
+            .lock()
+            .unwrap()
+            .entry(conversation_id.clone())
+        {
+            if Arc::ptr_eq(entry.get(), &cancel) {
+                entry.remove();
+            }
+        }
+
+        let payload = match result {
+            Ok(()) => DoneEvent {
+                conversation_id: conversation_id.clone(),
+                error: None,
+            },
+            Err(err) => DoneEvent {
+                conversation_id: conversation_id.clone(),
+                error: Some(err.clone()),
+            },
+        };
+        let event = if payload.error.is_some() {
+            "llm-error"
+        } else {
+            "llm-done"
+        };
+        let _ = window.emit(event, payload);
+    });
+}
+
+/// Signal the worker driving `conversation_id` to stop. A no-op if nothing is
+/// currently generating for that conversation.
+#[tauri::command]
+fn cancel(state: tauri::State<'_, AppState>, conversation_id: String) {
+    if let Some(flag) = state
+        .generations
+        .cancels
+        .lock()
+        .unwrap()
+        .get(&conversation_id)
+    {
+        flag.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Drive the model to completion, emitting one `llm-token` event per produced
+/// delta and bailing out early when `cancel` is raised. The actual decoding
+/// loop lives here so [`generate`] stays a thin command wrapper.
+async fn stream_tokens(
+    _model: &ModelRuntime,
+    window: &tauri::Window,
+    conversation_id: &str,
+    prompt: &str,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    // The model runtime is not wired up yet; echo the prompt back token by
+    // token so the streaming plumbing can be exercised end to end.
+    for word in prompt.split_whitespace() {
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        window
+            .emit(
+                "llm-token",
+                TokenEvent {
+                    conversation_id: conversation_id.to_string(),
+                    delta: format!("{} ", word),
+                },
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Options resolved from the command line and forwarded to the frontend so the
+/// app can boot directly into a pre-filled conversation. Supports invocations
+/// like `localmind --model ./llama.gguf --prompt "summarize" file.txt`.
+#[derive(Clone, Default, Serialize)]
+struct LaunchArgs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+}
+
+/// Read the CLI matches into a [`LaunchArgs`]. Missing or malformed arguments
+/// are simply left unset so a bare launch still boots into an empty session.
+fn parse_launch_args(app: &tauri::AppHandle) -> LaunchArgs {
+    let matches = match app.cli().matches() {
+        Ok(matches) => matches,
+        Err(_) => return LaunchArgs::default(),
     };
-    let _ = window.set_theme(theme_enum);
+    let as_string = |name: &str| {
+        matches
+            .args
+            .get(name)
+            .and_then(|arg| arg.value.as_str().map(str::to_string))
+    };
+    LaunchArgs {
+        model: as_string("model"),
+        prompt: as_string("prompt"),
+        file: as_string("file"),
+    }
+}
+
+/// Scan the configured directories for available model files. Stubbed until
+/// inference is wired up.
+fn discover_models() {}
+
+/// Memory-map the selected model's weights so they can be shared without a full
+/// copy. Stubbed until inference is wired up.
+fn mmap_weights() {}
+
+/// Run a short warm-up pass so the first real generation isn't penalised by
+/// lazy allocation. Stubbed until inference is wired up.
+fn warm_up() {}
+
+/// Perform the expensive startup work — model discovery, memory-mapping the
+/// weights and a short warm-up — reporting progress to the splashscreen as it
+/// goes. Runs on the async runtime so the UI thread is never blocked.
+async fn initialize(app: tauri::AppHandle) {
+    let emit = |value: u8, status: &str| {
+        let _ = app.emit(
+            "init-progress",
+            InitProgress {
+                value,
+                status: status.to_string(),
+            },
+        );
+    };
+
+    // Each stage announces itself, runs its (blocking) work off the async
+    // runtime with `spawn_blocking` — which yields between stages so the splash
+    // can paint — and reports the completed percentage. The work is stubbed
+    // until inference lands, but the progress reflects real awaited steps
+    // rather than a single synchronous burst.
+    emit(0, "Discovering models…");
+    let _ = tauri::async_runtime::spawn_blocking(discover_models).await;
+    emit(20, "Memory-mapping weights…");
+    let _ = tauri::async_runtime::spawn_blocking(mmap_weights).await;
+    emit(55, "Warming up…");
+    let _ = tauri::async_runtime::spawn_blocking(warm_up).await;
+    emit(100, "Ready");
+
+    // Hand the UI over: close the splash and reveal the main window.
+    if let Some(splash) = app.get_webview_window("splashscreen") {
+        let _ = splash.close();
+    }
+    if let Some(main) = app.get_webview_window("main") {
+        let _ = main.show();
+    }
+
+    // Boot the frontend into whatever the shell asked for, if anything.
+    let args = parse_launch_args(&app);
+    let _ = app.emit("cli-launch", args);
+}
+
+/// Toggle the visibility of the `main` window: reveal and focus it when it is
+/// hidden, hide it when it is visible.
+fn toggle_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// (Re)register the global summon/hide shortcut, replacing any previous
+/// binding. The accelerator is also persisted so it survives restarts.
+fn register_shortcut(app: &tauri::AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcut = app.global_shortcut();
+    shortcut.unregister_all().map_err(|e| e.to_string())?;
+    shortcut
+        .on_shortcut(accelerator, |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                toggle_main_window(app);
+            }
+        })
+        .map_err(|e| e.to_string())?;
+
+    update_settings(app, |settings| {
+        settings.shortcut = Some(accelerator.to_string());
+    })
+}
+
+/// Rebind the global shortcut from settings and persist the new accelerator.
+#[tauri::command]
+fn set_global_shortcut(app: tauri::AppHandle, accelerator: String) -> Result<(), String> {
+    register_shortcut(&app, &accelerator)
+}
+
+/// Return the options LocalMind was launched with. Exposed as a command so the
+/// frontend can pull the initial state on demand in addition to receiving the
+/// `cli-launch` event.
+#[tauri::command]
+fn launch_args(app: tauri::AppHandle) -> LaunchArgs {
+    parse_launch_args(&app)
+}
+
+/// The most recently used conversations, surfaced as tray shortcuts. Reads the
+/// shared [`ConversationStore`], newest first; each entry is an `(id, title)`
+/// pair.
+fn recent_conversations(app: &tauri::AppHandle) -> Vec<(String, String)> {
+    app.state::<AppState>()
+        .conversations
+        .lock()
+        .unwrap()
+        .conversations
+        .iter()
+        .rev()
+        .take(5)
+        .map(|c| (c.id.clone(), c.title.clone()))
+        .collect()
+}
+
+/// Build the tray menu from the current conversation store. Split out from
+/// [`build_tray`] so the menu can be rebuilt whenever the recent list changes.
+fn tray_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let new_chat = MenuItem::with_id(app, "new_chat", "New chat", true, None::<&str>)?;
+    let toggle = MenuItem::with_id(app, "toggle", "Show/Hide window", true, None::<&str>)?;
+
+    let recent = recent_conversations(app);
+    let recent_menu = if recent.is_empty() {
+        let empty =
+            MenuItem::with_id(app, "recent_empty", "No recent conversations", false, None::<&str>)?;
+        Submenu::with_items(app, "Recent", true, &[&empty])?
+    } else {
+        let items = recent
+            .iter()
+            .map(|(id, title)| {
+                MenuItem::with_id(app, format!("recent:{id}"), title, true, None::<&str>)
+            })
+            .collect::<tauri::Result<Vec<_>>>()?;
+        let refs = items
+            .iter()
+            .map(|i| i as &dyn tauri::menu::IsMenuItem<_>)
+            .collect::<Vec<_>>();
+        Submenu::with_items(app, "Recent", true, &refs)?
+    };
+
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let sep = PredefinedMenuItem::separator(app)?;
+    Menu::with_items(app, &[&new_chat, &toggle, &recent_menu, &sep, &quit])
+}
+
+/// Rebuild the tray menu in place so a freshly recorded conversation shows up
+/// under "Recent" without recreating the icon.
+fn refresh_tray_menu(app: &tauri::AppHandle) -> tauri::Result<()> {
+    if let Some(tray) = app.tray_by_id("main") {
+        tray.set_menu(Some(tray_menu(app)?))?;
+    }
+    Ok(())
+}
+
+/// Record a conversation as recently used so it appears in the tray's "Recent"
+/// submenu. Re-recording an existing id moves it back to the top.
+#[tauri::command]
+fn record_conversation(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: String,
+    title: String,
+) -> Result<(), String> {
+    {
+        let mut store = state.conversations.lock().unwrap();
+        store.conversations.retain(|c| c.id != id);
+        store.conversations.push(Conversation { id, title });
+    }
+    refresh_tray_menu(&app).map_err(|e| e.to_string())
+}
+
+/// Choose whether closing the window hides it to the tray (the default) or
+/// exits the app, persisting the preference.
+#[tauri::command]
+fn set_minimize_to_tray(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.minimize_to_tray = Some(enabled);
+    })
+}
+
+/// Build the tray icon and its menu. Menu clicks are translated into events the
+/// frontend already understands (`new-chat`, `open-conversation`) or act on the
+/// window directly.
+fn build_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
+    TrayIconBuilder::with_id("main")
+        .tooltip("LocalMind")
+        .menu(&tray_menu(app)?)
+        .on_menu_event(|app, event| {
+            let id = event.id().as_ref();
+            match id {
+                "new_chat" => {
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                    let _ = app.emit("new-chat", ());
+                }
+                "toggle" => toggle_main_window(app),
+                "quit" => app.exit(0),
+                other => {
+                    if let Some(conversation_id) = other.strip_prefix("recent:") {
+                        if let Some(window) = app.get_webview_window("main") {
+                            let _ = window.show();
+                            let _ = window.set_focus();
+                        }
+                        let _ = app.emit("open-conversation", conversation_id.to_string());
+                    }
+                }
+            }
+        })
+        .build(app)?;
+    Ok(())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -20,8 +537,77 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet, set_app_theme])
+        .plugin(tauri_plugin_cli::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .manage(AppState::default())
+        .setup(|app| {
+            let handle = app.handle().clone();
+
+            // Seed the shared config from disk so commands see the persisted
+            // settings without re-reading the file each time.
+            *app.state::<AppState>().config.lock().unwrap() = load_settings(&handle);
+
+            // Restore the saved shortcut, or fall back to the default chord.
+            let accelerator = current_settings(&handle)
+                .shortcut
+                .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string());
+            if let Err(err) = register_shortcut(&handle, &accelerator) {
+                eprintln!("failed to register global shortcut: {err}");
+            }
+
+            // Reapply the saved theme, defaulting to following the OS.
+            let theme_pref = current_settings(&handle)
+                .theme
+                .unwrap_or_else(|| "system".to_string());
+            apply_theme(&handle, &theme_pref);
+
+            // In system mode, mirror OS theme changes straight to the frontend
+            // so the CSS updates live.
+            if let Some(window) = handle.get_webview_window("main") {
+                let emitter = handle.clone();
+                window.on_window_event(move |event| match event {
+                    tauri::WindowEvent::ThemeChanged(theme) => {
+                        let pref = current_settings(&emitter).theme;
+                        let following_os = !matches!(pref.as_deref(), Some("dark") | Some("light"));
+                        if following_os {
+                            let name = match theme {
+                                tauri::Theme::Dark => "dark",
+                                _ => "light",
+                            };
+                            let _ = emitter.emit("theme-changed", name);
+                        }
+                    }
+                    // Hide to tray rather than exit, unless the user opted out.
+                    tauri::WindowEvent::CloseRequested { api, .. } => {
+                        let to_tray = current_settings(&emitter).minimize_to_tray.unwrap_or(true);
+                        if to_tray {
+                            api.prevent_close();
+                            if let Some(window) = emitter.get_webview_window("main") {
+                                let _ = window.hide();
+                            }
+                        }
+                    }
+                    _ => {}
+                });
+            }
+
+            if let Err(err) = build_tray(&handle) {
+                eprintln!("failed to build tray icon: {err}");
+            }
+
+            tauri::async_runtime::spawn(initialize(handle));
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            set_app_theme,
+            generate,
+            cancel,
+            launch_args,
+            set_global_shortcut,
+            record_conversation,
+            set_minimize_to_tray
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }